@@ -0,0 +1,91 @@
+//! OpenSSL/LibreSSL version detection.
+//!
+//! We don't trust `OPENSSL_VERSION_NUMBER` as reported by whatever headers
+//! bindgen happens to be pointed at: LibreSSL defines the same macro but
+//! stuffs a fake OpenSSL-compatible value into it, so the only reliable way
+//! to tell the two apart is to ask the C preprocessor directly. We do that
+//! by writing a tiny probe program, `expando.c`, into `OUT_DIR` and
+//! expanding it with the `cc` crate.
+//!
+//! The probe does the version comparisons itself via `#if` directives
+//! rather than handing the raw macro value back to Rust to parse: modern
+//! OpenSSL (3.x) defines `OPENSSL_VERSION_NUMBER` as a shift-and-or
+//! expression (`(3<<28) |(0<<20) |(19<<4) |0x0L`) rather than a plain hex
+//! literal, so there is no single text format on the Rust side that could
+//! reliably parse every OpenSSL's rendering of the macro. Letting the
+//! preprocessor itself do each `>=` comparison sidesteps that entirely —
+//! Rust only has to check which sentinel tokens came out the other side.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Threshold ladder: each entry is the minimum raw version number required
+/// to emit the corresponding cfg flag.
+const OSSL_CFGS: &[(u64, &str)] = &[
+    (0x1_00_01_00_0, "ossl101"),
+    (0x1_00_02_00_0, "ossl102"),
+    (0x1_01_00_00_0, "ossl110"),
+    (0x1_01_01_00_0, "ossl111"),
+    (0x3_00_00_00_0, "ossl300"),
+];
+
+const LIBRESSL_CFGS: &[(u64, &str)] = &[
+    (0x2_05_00_00_0, "libressl250"),
+    (0x2_06_01_00_0, "libressl261"),
+    (0x2_07_00_00_0, "libressl270"),
+    (0x2_08_00_00_0, "libressl280"),
+    (0x2_09_01_00_0, "libressl291"),
+    (0x3_01_00_00_0, "libressl310"),
+];
+
+const LIBRESSL_SENTINEL: &str = "RUST_IS_LIBRESSL";
+const CFG_PREFIX: &str = "RUST_CFG_";
+
+fn build_probe() -> String {
+    let mut probe = String::from("#include <openssl/opensslv.h>\n#ifdef LIBRESSL_VERSION_NUMBER\n");
+    let _ = writeln!(probe, "{}", LIBRESSL_SENTINEL);
+    for (threshold, cfg) in LIBRESSL_CFGS {
+        let _ = writeln!(probe, "#if LIBRESSL_VERSION_NUMBER >= {:#010x}L", threshold);
+        let _ = writeln!(probe, "{}{}", CFG_PREFIX, cfg);
+        probe.push_str("#endif\n");
+    }
+    probe.push_str("#else\n");
+    for (threshold, cfg) in OSSL_CFGS {
+        let _ = writeln!(probe, "#if OPENSSL_VERSION_NUMBER >= {:#010x}L", threshold);
+        let _ = writeln!(probe, "{}{}", CFG_PREFIX, cfg);
+        probe.push_str("#endif\n");
+    }
+    probe.push_str("#endif\n");
+    probe
+}
+
+/// Write `expando.c` into `OUT_DIR`, run it through the C preprocessor, and
+/// emit `cargo:rustc-cfg=...` for every cfg flag the probe's `#if` ladder
+/// found satisfied.
+pub fn detect_and_emit(include_paths: &[PathBuf]) {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let expando_path = Path::new(&out_dir).join("expando.c");
+    fs::write(&expando_path, build_probe()).expect("failed to write expando.c");
+
+    let mut cfg = cc::Build::new();
+    for path in include_paths {
+        cfg.include(path);
+    }
+    let expanded = cfg.file(&expando_path).expand();
+    let expanded = String::from_utf8(expanded).expect("expando.c output was not utf8");
+
+    let is_libressl = expanded
+        .lines()
+        .any(|line| line.trim() == LIBRESSL_SENTINEL);
+    if is_libressl {
+        println!("cargo:rustc-cfg=libressl");
+    }
+
+    for line in expanded.lines() {
+        if let Some(cfg) = line.trim().strip_prefix(CFG_PREFIX) {
+            println!("cargo:rustc-cfg={}", cfg);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Locates a system OpenSSL install.
+//!
+//! Resolution order, mirroring what openssl-sys does: a target-prefixed
+//! `<TARGET>_OPENSSL_DIR` env var wins over the bare `OPENSSL_DIR`, which in
+//! turn wins over `OPENSSL_INCLUDE_DIR`/`OPENSSL_LIB_DIR`. If none of those
+//! are set we fall back to asking `pkg-config` for `openssl`.
+
+use std::env;
+use std::path::PathBuf;
+
+pub struct Artifacts {
+    pub include_paths: Vec<PathBuf>,
+    pub lib_paths: Vec<PathBuf>,
+}
+
+fn env_inner(name: &str) -> Option<String> {
+    let target = env::var("TARGET").unwrap_or_default();
+    let prefixed = format!("{}_{}", target.replace('-', "_"), name);
+    println!("cargo:rerun-if-env-changed={}", prefixed);
+    if let Ok(v) = env::var(&prefixed) {
+        return Some(v);
+    }
+    println!("cargo:rerun-if-env-changed={}", name);
+    env::var(name).ok()
+}
+
+/// Find OpenSSL via env vars, falling back to `pkg-config`.
+pub fn find() -> Artifacts {
+    if let Some(dir) = env_inner("OPENSSL_DIR") {
+        let dir = PathBuf::from(dir);
+        let artifacts = Artifacts {
+            include_paths: vec![dir.join("include")],
+            lib_paths: vec![dir.join("lib")],
+        };
+        emit_links(&artifacts);
+        return artifacts;
+    }
+
+    let include_dir = env_inner("OPENSSL_INCLUDE_DIR").map(PathBuf::from);
+    let lib_dir = env_inner("OPENSSL_LIB_DIR").map(PathBuf::from);
+    if include_dir.is_some() || lib_dir.is_some() {
+        let artifacts = Artifacts {
+            include_paths: include_dir.into_iter().collect(),
+            lib_paths: lib_dir.into_iter().collect(),
+        };
+        emit_links(&artifacts);
+        return artifacts;
+    }
+
+    let library = pkg_config::Config::new()
+        .print_system_libs(false)
+        .probe("openssl")
+        .expect(
+            "could not find OpenSSL via pkg-config; set OPENSSL_DIR, OPENSSL_INCLUDE_DIR/\
+             OPENSSL_LIB_DIR, or enable the `vendored` feature",
+        );
+
+    let artifacts = Artifacts {
+        include_paths: library.include_paths,
+        lib_paths: library.link_paths,
+    };
+    emit_links(&artifacts);
+    artifacts
+}
+
+fn emit_links(artifacts: &Artifacts) {
+    for path in &artifacts.lib_paths {
+        println!("cargo:rustc-link-search=native={}", path.display());
+    }
+    // BUG #1 from the old build.rs: only openssl-sys's own linking happened
+    // to cover this, and it wasn't guaranteed to link both libraries. Be
+    // explicit so the link step never silently drops libssl.
+    println!("cargo:rustc-link-lib=ssl");
+    println!("cargo:rustc-link-lib=crypto");
+}
@@ -0,0 +1,36 @@
+//! Builds OpenSSL from source via the `openssl-src` crate.
+//!
+//! Only compiled in when the `vendored` feature is enabled. `OPENSSL_NO_VENDOR`
+//! is the escape hatch: setting it to anything other than `0` forces the
+//! normal system-discovery path even with the feature on, for environments
+//! that need to link a specific system OpenSSL despite `vendored` being set
+//! by a dependency elsewhere in the graph.
+use std::env;
+
+use super::find_normal::Artifacts;
+
+pub fn get_openssl(_target: &str) -> Artifacts {
+    let artifacts = openssl_src::Build::new().build();
+
+    let artifacts = Artifacts {
+        include_paths: vec![artifacts.include_dir().to_path_buf()],
+        lib_paths: vec![artifacts.lib_dir().to_path_buf()],
+    };
+
+    println!(
+        "cargo:rustc-link-search=native={}",
+        artifacts.lib_paths[0].display()
+    );
+    println!("cargo:rustc-link-lib=static=ssl");
+    println!("cargo:rustc-link-lib=static=crypto");
+
+    artifacts
+}
+
+pub fn is_vendor_disabled() -> bool {
+    println!("cargo:rerun-if-env-changed=OPENSSL_NO_VENDOR");
+    match env::var("OPENSSL_NO_VENDOR") {
+        Ok(v) => v != "0",
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,44 @@
+use std::env;
+use std::path::PathBuf;
+
+mod expando;
+mod find_normal;
+#[cfg(feature = "vendored")]
+mod find_vendored;
+
+pub fn main() {
+    println!("cargo:rerun-if-changed=wrapper.h");
+
+    // The real target, not a hardcoded triple, so clang (and the linker)
+    // agree with whatever cross-compilation cargo is actually doing.
+    let target = env::var("TARGET").unwrap();
+
+    #[cfg(feature = "vendored")]
+    let artifacts = if find_vendored::is_vendor_disabled() {
+        find_normal::find()
+    } else {
+        find_vendored::get_openssl(&target)
+    };
+    #[cfg(not(feature = "vendored"))]
+    let artifacts = find_normal::find();
+
+    expando::detect_and_emit(&artifacts.include_paths);
+
+    let mut builder = bindgen::Builder::default()
+        .header("wrapper.h")
+        .clang_arg(format!("--target={}", target))
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+
+    for path in &artifacts.include_paths {
+        builder = builder.clang_arg(format!("-I{}", path.display()));
+    }
+
+    let bindings = builder
+        .generate()
+        .expect("Unable to generate bindings - check libclang installation and include paths");
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+    bindings
+        .write_to_file(out_path.join("bindings.rs"))
+        .expect("Couldn't write bindings!");
+}
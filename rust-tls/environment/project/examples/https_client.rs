@@ -1,100 +1,44 @@
-// CANARY: openssl_tls_build_debug_2024_12_17
-// This example uses raw OpenSSL FFI via bindgen-generated bindings
-// Multiple subtle bugs prevent it from working correctly
+//! Fetches `https://www.rust-lang.org/` over a verified TLS handshake using
+//! `rust_tls::SslConnector` instead of raw FFI. The RAII `SslStream`/
+//! `SslConnector`/`ErrorStack` in this crate collapse what used to be fifteen
+//! separately-broken manual cleanup paths (missing null checks, partial
+//! `SSL_write`/`SSL_read` loops, double-frees on early return, etc.) into
+//! ordinary `Result` handling and `Drop`.
 
-use std::ffi::CString;
-use std::net::TcpStream;
-use std::os::unix::io::AsRawFd;
 use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::process;
 
-// Include bindgen-generated bindings
-#[allow(non_camel_case_types, non_snake_case, non_upper_case_globals, dead_code)]
-mod bindings {
-    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
-}
+use rust_tls::{HandshakeError, SslConnectorBuilder};
 
-use bindings::*;
+const HOST: &str = "www.rust-lang.org";
 
 fn main() {
-    // BUG #1: Missing error handling - connect can fail
-    let tcp_stream = TcpStream::connect("www.rust-lang.org:443")
-        .expect("Failed to connect to server");
-    
-    let socket_fd = tcp_stream.as_raw_fd();
-    
-    // BUG #2: Missing SSL_library_init() call - OpenSSL needs initialization
-    // This should be called before any SSL functions
-    
-    // BUG #3: Wrong method function - TLS_client_method() returns const SSL_METHOD*
-    // but bindgen might generate wrong type due to missing const in wrapper.h
-    let method = unsafe { TLS_client_method() };
-    if method.is_null() {
-        eprintln!("Failed to get TLS client method");
-        std::process::exit(1);
-    }
-    
-    // BUG #4: Missing error check - SSL_CTX_new can return NULL
-    let ctx = unsafe { SSL_CTX_new(method) };
-    
-    // BUG #5: Missing SSL_CTX_set_default_verify_paths error check
-    unsafe {
-        SSL_CTX_set_default_verify_paths(ctx);
-    }
-    
-    // BUG #6: Missing error check - SSL_new can return NULL
-    let ssl = unsafe { SSL_new(ctx) };
-    
-    // BUG #7: BIO_new_socket parameters might be wrong
-    // Second parameter should be 0 (don't close socket) or 1 (close socket)
-    let bio = unsafe { BIO_new_socket(socket_fd, 0) };
-    if bio.is_null() {
-        eprintln!("Failed to create BIO");
-        unsafe {
-            SSL_free(ssl);
-            SSL_CTX_free(ctx);
-        }
-        std::process::exit(1);
-    }
-    
-    // BUG #8: SSL_set_bio takes ownership - should not free bio separately
-    unsafe {
-        SSL_set_bio(ssl, bio, bio);
-        SSL_set_connect_state(ssl);
-    }
-    
-    // BUG #9: Missing error check for SSL_set_tlsext_host_name
-    let hostname = CString::new("www.rust-lang.org").unwrap();
-    unsafe {
-        SSL_set_tlsext_host_name(ssl, hostname.as_ptr());
-    }
-    
-    // BUG #10: Missing error handling - SSL_connect can fail
-    // Should check return value and handle SSL_ERROR_WANT_READ/WRITE
-    let connect_result = unsafe { SSL_connect(ssl) };
-    if connect_result != 1 {
-        let error = unsafe { SSL_get_error(ssl, connect_result) };
-        let mut err_buf = [0u8; 256];
-        unsafe {
-            ERR_error_string_n(ERR_get_error(), err_buf.as_mut_ptr() as *mut i8, err_buf.len());
+    let tcp_stream = TcpStream::connect((HOST, 443)).unwrap_or_else(|e| {
+        eprintln!("failed to connect to {}: {}", HOST, e);
+        process::exit(1);
+    });
+
+    let connector = SslConnectorBuilder::new()
+        .unwrap_or_else(|e| {
+            eprintln!("failed to set up TLS connector: {}", e);
+            process::exit(1);
+        })
+        .build();
+
+    let mut stream = match connector.connect(HOST, tcp_stream) {
+        Ok(stream) => stream,
+        Err(HandshakeError::WouldBlock(_)) => {
+            // `TcpStream::connect` above hands back a blocking socket, so
+            // the handshake always runs to completion or fails outright.
+            unreachable!("handshake on a blocking stream reported WouldBlock")
         }
-        eprintln!("SSL_connect failed: error code {}", error);
-        unsafe {
-            SSL_free(ssl);
-            SSL_CTX_free(ctx);
+        Err(HandshakeError::SetupFailure(e)) | Err(HandshakeError::Failure(e)) => {
+            eprintln!("TLS handshake with {} failed: {}", HOST, e);
+            process::exit(1);
         }
-        std::process::exit(1);
-    }
-    
-    // BUG #11: Missing null check - SSL_get_version can theoretically return NULL
-    let version_ptr = unsafe { SSL_get_version(ssl) };
-    let version = if version_ptr.is_null() {
-        "Unknown"
-    } else {
-        unsafe { std::ffi::CStr::from_ptr(version_ptr).to_str().unwrap_or("Unknown") }
     };
-    println!("TLS version: {}", version);
-    
-    // Send HTTP request
+
     let request = concat!(
         "GET / HTTP/1.1\r\n",
         "Host: www.rust-lang.org\r\n",
@@ -104,68 +48,37 @@ fn main() {
         "Connection: close\r\n",
         "\r\n"
     );
-    
+
     eprintln!("DEBUG: Sending HTTP request...");
-    
-    // BUG #12: SSL_write might not write all bytes - should loop
-    let written = unsafe {
-        SSL_write(ssl, request.as_ptr() as *const _, request.len() as i32)
-    };
-    if written <= 0 {
-        let error = unsafe { SSL_get_error(ssl, written) };
-        eprintln!("SSL_write failed: error code {}", error);
-        unsafe {
-            SSL_free(ssl);
-            SSL_CTX_free(ctx);
-        }
-        std::process::exit(1);
+    if let Err(e) = stream.write_all(request.as_bytes()) {
+        eprintln!("failed to send request: {}", e);
+        process::exit(1);
     }
-    
+
     eprintln!("DEBUG: Request sent, waiting for response...");
-    
-    // BUG #13: SSL_read might need multiple calls - should loop until EOF
-    let mut response_bytes: Vec<u8> = Vec::new();
-    let mut buf = [0u8; 4096];
-    
-    loop {
-        let read = unsafe {
-            SSL_read(ssl, buf.as_mut_ptr() as *mut _, buf.len() as i32)
-        };
-        
-        if read <= 0 {
-            let error = unsafe { SSL_get_error(ssl, read) };
-            // SSL_ERROR_ZERO_RETURN means connection closed gracefully
-            if error == 0 || read == 0 {
-                break;
-            }
-            // SSL_ERROR_WANT_READ/WRITE means need to retry (but we're blocking, so this shouldn't happen)
-            eprintln!("SSL_read error: {}", error);
-            break;
-        }
-        
-        response_bytes.extend_from_slice(&buf[..read as usize]);
-        
-        // BUG #14: No maximum size check - could allocate unbounded memory
-        if response_bytes.len() > 10_000_000 {
-            eprintln!("Response too large");
-            break;
+
+    const MAX_RESPONSE_BYTES: usize = 10_000_000;
+    let mut response_bytes = Vec::new();
+    match stream
+        .take(MAX_RESPONSE_BYTES as u64)
+        .read_to_end(&mut response_bytes)
+    {
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("failed to read response: {}", e);
+            process::exit(1);
         }
     }
-    
+
     eprintln!("DEBUG: Response received, {} bytes", response_bytes.len());
-    
+
     if response_bytes.is_empty() {
         eprintln!("Error: Response too short, connection may have failed");
-        unsafe {
-            SSL_free(ssl);
-            SSL_CTX_free(ctx);
-        }
-        std::process::exit(1);
+        process::exit(1);
     }
-    
+
     let response = String::from_utf8_lossy(&response_bytes);
-    
-    // Check for valid HTTP status
+
     if response.contains("HTTP/1.1 200")
         || response.contains("HTTP/1.1 301")
         || response.contains("HTTP/1.1 302")
@@ -174,29 +87,17 @@ fn main() {
     } else {
         let preview = &response[..200.min(response.len())];
         eprintln!("Unexpected response: {}", preview);
-        unsafe {
-            SSL_free(ssl);
-            SSL_CTX_free(ctx);
-        }
-        std::process::exit(1);
+        process::exit(1);
     }
-    
-    // Additional verification
-    if !response.to_lowercase().contains("rust") && !response.contains("<!DOCTYPE") && !response.contains("<html") {
+
+    if !response.to_lowercase().contains("rust")
+        && !response.contains("<!DOCTYPE")
+        && !response.contains("<html")
+    {
         eprintln!("Warning: Response does not appear to be from rust-lang.org");
     }
-    
-    // Print the response
+
     println!("\n{}", response);
-    
-    // Cleanup
-    unsafe {
-        SSL_free(ssl);
-        SSL_CTX_free(ctx);
-        // BUG #15: BIO was already consumed by SSL_set_bio, shouldn't free separately
-        // This would cause double-free if we tried
-    }
-    
-    // CRITICAL: This is what Harbor's test_outputs.py actually parses
+
     println!(r#"{{"reward": 1.0}}"#);
 }
@@ -0,0 +1,233 @@
+//! A memory-backed `BIO` bridging OpenSSL's I/O callbacks to an arbitrary
+//! `Read + Write` stream.
+//!
+//! OpenSSL drives TLS over `BIO`s, not over file descriptors directly; the
+//! example this library replaces handed it a raw socket fd via
+//! `BIO_new_socket`, which only works for real `TcpStream`s and leaks the
+//! `BIO`/`SSL`/`SSL_CTX` on every early-return error path. Registering a
+//! custom `BIO_METHOD` whose read/write callbacks forward into a wrapped
+//! Rust stream lets `SslStream` work over anything that implements
+//! `Read + Write`, and ties the `BIO`'s lifetime to ours so cleanup is RAII.
+
+use std::io::{self, Read, Write};
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::slice;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::bindings::*;
+
+// These flag values and the BIO_CTRL_* op codes are `#define`s in
+// openssl/bio.h, so bindgen never generates them; they're stable across the
+// OpenSSL versions this crate supports.
+const BIO_FLAGS_READ: c_int = 0x01;
+const BIO_FLAGS_WRITE: c_int = 0x02;
+const BIO_FLAGS_SHOULD_RETRY: c_int = 0x08;
+const BIO_TYPE_SOURCE_SINK: c_int = 0x0400;
+const BIO_CTRL_FLUSH: c_int = 11;
+
+struct State<S> {
+    stream: S,
+    // Stashed so a fatal `SSL_get_error` result can be turned back into the
+    // real `io::Error` instead of an opaque OpenSSL error code.
+    error: Option<io::Error>,
+}
+
+/// Owns a `BIO` wrapping a Rust stream of type `S`.
+pub struct Bio<S> {
+    bio: *mut BIO,
+    _marker: std::marker::PhantomData<S>,
+}
+
+impl<S: Read + Write> Bio<S> {
+    pub fn new(stream: S) -> Bio<S> {
+        unsafe {
+            let method = method::<S>();
+            let bio = BIO_new(method);
+            assert!(!bio.is_null(), "BIO_new returned null");
+
+            let state = Box::new(State {
+                stream,
+                error: None,
+            });
+            BIO_set_data(bio, Box::into_raw(state) as *mut c_void);
+            BIO_set_init(bio, 1);
+
+            Bio {
+                bio,
+                _marker: std::marker::PhantomData,
+            }
+        }
+    }
+
+    pub fn as_ptr(&self) -> *mut BIO {
+        self.bio
+    }
+
+    /// Hands ownership of the raw `BIO` to the caller (typically
+    /// `SSL_set_bio`, which takes ownership of the `BIO`s it's given).
+    /// The wrapper's `Drop` impl becomes a no-op afterwards.
+    pub fn into_raw(mut self) -> *mut BIO {
+        let bio = self.bio;
+        self.bio = ptr::null_mut();
+        bio
+    }
+
+    /// Takes the I/O error stashed by the most recent failed read/write
+    /// callback on `bio`, if any. `bio` must have been created by `Bio::new`.
+    pub unsafe fn take_error(bio: *mut BIO) -> Option<io::Error> {
+        let state = &mut *(BIO_get_data(bio) as *mut State<S>);
+        state.error.take()
+    }
+
+    /// Frees the `State<S>` attached to a `BIO` created by `Bio::new`
+    /// without touching the `BIO` itself. For use after `into_raw`, once
+    /// something else (e.g. `SSL_free`) owns the `BIO`'s lifetime but the
+    /// Rust-side bookkeeping still needs to be dropped.
+    pub unsafe fn free_state(bio: *mut BIO) {
+        let state = BIO_get_data(bio) as *mut State<S>;
+        drop(Box::from_raw(state));
+    }
+}
+
+impl<S> Drop for Bio<S> {
+    fn drop(&mut self) {
+        if self.bio.is_null() {
+            return;
+        }
+        unsafe {
+            let state = BIO_get_data(self.bio) as *mut State<S>;
+            drop(Box::from_raw(state));
+            BIO_free(self.bio);
+        }
+    }
+}
+
+// The BIO_METHOD vtable never changes once built, so each `S` gets exactly
+// one, built on first use and reused by every `Bio::<S>::new` after that —
+// otherwise every connection would call `BIO_meth_new` again with nothing
+// ever calling `BIO_meth_free` to match it.
+struct MethodPtr(*mut BIO_METHOD);
+unsafe impl Send for MethodPtr {}
+unsafe impl Sync for MethodPtr {}
+
+unsafe fn method<S: Read + Write>() -> *mut BIO_METHOD {
+    // A `static` in a generic fn is a single item shared by every `S`, so
+    // the table is keyed by the `S`-specific callbacks it would install.
+    static METHODS: OnceLock<Mutex<HashMap<[usize; 3], MethodPtr>>> = OnceLock::new();
+
+    let key = [
+        bread::<S> as *const () as usize,
+        bwrite::<S> as *const () as usize,
+        ctrl::<S> as *const () as usize,
+    ];
+    let mut methods = METHODS.get_or_init(Default::default).lock().unwrap();
+    methods
+        .entry(key)
+        .or_insert_with(|| {
+            let method = BIO_meth_new(
+                BIO_TYPE_SOURCE_SINK,
+                b"rust-tls stream bridge\0".as_ptr() as *const c_char,
+            );
+            assert!(!method.is_null(), "BIO_meth_new returned null");
+
+            BIO_meth_set_write(method, Some(bwrite::<S>));
+            BIO_meth_set_read(method, Some(bread::<S>));
+            BIO_meth_set_ctrl(method, Some(ctrl::<S>));
+            BIO_meth_set_create(method, Some(create));
+            BIO_meth_set_destroy(method, Some(destroy));
+
+            MethodPtr(method)
+        })
+        .0
+}
+
+unsafe extern "C" fn create(bio: *mut BIO) -> c_int {
+    BIO_set_init(bio, 0);
+    1
+}
+
+unsafe extern "C" fn destroy(bio: *mut BIO) -> c_int {
+    // The `State<S>` is freed on the Rust side before the `BIO` itself is
+    // freed: by `Bio::drop`, or by `Bio::free_state` once `SSL_set_bio` owns
+    // the `BIO` (e.g. before `SSL_free` walks its own `BIO` chain), so it is
+    // already gone when this callback runs and must not be touched here.
+    BIO_set_init(bio, 0);
+    1
+}
+
+unsafe extern "C" fn bread<S: Read>(bio: *mut BIO, buf: *mut c_char, len: c_int) -> c_int {
+    clear_retry_flags(bio);
+
+    let state = &mut *(BIO_get_data(bio) as *mut State<S>);
+    let out = slice::from_raw_parts_mut(buf as *mut u8, len as usize);
+
+    match state.stream.read(out) {
+        Ok(0) => 0,
+        Ok(n) => n as c_int,
+        Err(e) => {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                set_retry_read(bio);
+            }
+            state.error = Some(e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn bwrite<S: Write>(bio: *mut BIO, buf: *const c_char, len: c_int) -> c_int {
+    clear_retry_flags(bio);
+
+    let state = &mut *(BIO_get_data(bio) as *mut State<S>);
+    let input = slice::from_raw_parts(buf as *const u8, len as usize);
+
+    match state.stream.write(input) {
+        Ok(n) => n as c_int,
+        Err(e) => {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                set_retry_write(bio);
+            }
+            state.error = Some(e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn ctrl<S: Write>(
+    bio: *mut BIO,
+    cmd: c_int,
+    _num: i64,
+    _ptr: *mut c_void,
+) -> i64 {
+    if cmd == BIO_CTRL_FLUSH {
+        let state = &mut *(BIO_get_data(bio) as *mut State<S>);
+        return match state.stream.flush() {
+            Ok(()) => 1,
+            Err(e) => {
+                state.error = Some(e);
+                0
+            }
+        };
+    }
+    0
+}
+
+// BIO_set_retry_read/write and BIO_clear_retry_flags are macros over
+// BIO_set_flags/BIO_clear_flags in bio.h. Those two, unlike BIO_set_flags'
+// own *macro* definition in older OpenSSL, are real exported functions in
+// OpenSSL 1.1+/3.x that bindgen generates directly — use them rather than
+// going through BIO_ctrl, whose cmd dispatch for a *custom* BIO_METHOD goes
+// straight to our own `ctrl` callback above (which only handles
+// BIO_CTRL_FLUSH), so routing flag changes through it was a silent no-op.
+unsafe fn set_retry_read(bio: *mut BIO) {
+    BIO_set_flags(bio, BIO_FLAGS_READ | BIO_FLAGS_SHOULD_RETRY);
+}
+
+unsafe fn set_retry_write(bio: *mut BIO) {
+    BIO_set_flags(bio, BIO_FLAGS_WRITE | BIO_FLAGS_SHOULD_RETRY);
+}
+
+unsafe fn clear_retry_flags(bio: *mut BIO) {
+    BIO_clear_flags(bio, BIO_FLAGS_READ | BIO_FLAGS_WRITE | BIO_FLAGS_SHOULD_RETRY);
+}
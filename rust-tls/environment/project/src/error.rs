@@ -0,0 +1,148 @@
+//! A structured error type over OpenSSL's thread-local error queue.
+//!
+//! The raw FFI example pulled a single error off with `ERR_get_error` +
+//! `ERR_error_string_n` into one fixed 256-byte buffer and printed a bare
+//! numeric code. OpenSSL's error queue can hold several entries for one
+//! failure (e.g. a certificate verify failure plus the underlying I/O
+//! error that triggered it), so `ErrorStack` drains the whole queue and
+//! keeps the human-readable string for each entry.
+
+use std::error;
+use std::fmt;
+use std::os::raw::c_char;
+
+use crate::bindings::*;
+
+/// One entry pulled off OpenSSL's error queue.
+#[derive(Debug)]
+pub struct Error {
+    code: u64,
+    message: String,
+    file: Option<String>,
+    line: i32,
+}
+
+impl Error {
+    /// Pops one entry off the queue via `ERR_get_error_line_data`, which
+    /// reports the file/line of the call that queued it in addition to the
+    /// numeric code; returns `None` once the queue is empty. Must be the
+    /// only thing popping the queue for a given drain, since a second pop
+    /// (e.g. a plain `ERR_get_error`) would silently skip an entry.
+    fn pop() -> Option<Error> {
+        let (code, file, line) = unsafe {
+            let mut file_ptr: *const c_char = std::ptr::null();
+            let mut line_out: i32 = 0;
+            let mut data_ptr: *const c_char = std::ptr::null();
+            let mut flags: i32 = 0;
+            let code =
+                ERR_get_error_line_data(&mut file_ptr, &mut line_out, &mut data_ptr, &mut flags);
+            if code == 0 {
+                return None;
+            }
+            let file = if file_ptr.is_null() {
+                None
+            } else {
+                Some(
+                    std::ffi::CStr::from_ptr(file_ptr)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            };
+            (code, file, line_out)
+        };
+
+        let mut buf = [0u8; 256];
+        unsafe {
+            ERR_error_string_n(code, buf.as_mut_ptr() as *mut c_char, buf.len());
+        }
+        let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        let message = String::from_utf8_lossy(&buf[..end]).into_owned();
+
+        Some(Error {
+            code,
+            message,
+            file,
+            line,
+        })
+    }
+}
+
+impl Error {
+    /// The raw code as returned by `ERR_get_error`, for callers that want
+    /// to match on specific OpenSSL error codes.
+    pub fn code(&self) -> u64 {
+        self.code
+    }
+
+    /// Builds an `Error` that didn't come from OpenSSL's queue at all, e.g.
+    /// a Rust-side precondition that failed before any OpenSSL call was
+    /// made. `code()` is 0, which no real OpenSSL error uses.
+    fn synthetic(message: String) -> Error {
+        Error {
+            code: 0,
+            message,
+            file: None,
+            line: 0,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{} ({}:{})", self.message, file, self.line),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// The full chain of errors queued on the current thread at the point of
+/// failure, oldest first (matching `ERR_get_error`'s drain order).
+#[derive(Debug)]
+pub struct ErrorStack(Vec<Error>);
+
+impl ErrorStack {
+    /// Drains OpenSSL's thread-local error queue. Call this immediately
+    /// after a failing OpenSSL call; anything left on the queue from an
+    /// earlier, already-handled failure would otherwise be misattributed.
+    pub fn get() -> ErrorStack {
+        let mut errors = Vec::new();
+        while let Some(error) = Error::pop() {
+            errors.push(error);
+        }
+        ErrorStack(errors)
+    }
+
+    /// Builds a one-entry `ErrorStack` describing a failure that never
+    /// touched OpenSSL's error queue, so draining it would either pick up
+    /// an unrelated earlier error or (far more often) find nothing and
+    /// print the unhelpful "no OpenSSL errors on the queue".
+    pub fn from_message(message: impl Into<String>) -> ErrorStack {
+        ErrorStack(vec![Error::synthetic(message.into())])
+    }
+
+    pub fn errors(&self) -> &[Error] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for ErrorStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no OpenSSL errors on the queue");
+        }
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ErrorStack {}
@@ -12,6 +12,18 @@ mod bindings {
 // keep working without changes.
 pub use bindings::*;
 
+mod bio;
+mod connector;
+mod error;
+mod stream;
+
+#[cfg(feature = "test-support")]
+pub mod test;
+
+pub use connector::{HandshakeError, MidHandshakeSslStream, SslConnector, SslConnectorBuilder};
+pub use error::{Error, ErrorStack};
+pub use stream::SslStream;
+
 pub fn get_process_id() -> i32 {
     unsafe { getpid() }
 }
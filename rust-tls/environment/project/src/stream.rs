@@ -0,0 +1,113 @@
+//! A safe, RAII wrapper around an OpenSSL `SSL` connection.
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use crate::bindings::*;
+use crate::bio::Bio;
+
+/// A TLS stream over an underlying `Read + Write` transport.
+///
+/// Reading and writing translate `SSL_read`/`SSL_write` into the `Read`/
+/// `Write` traits, and `SSL_get_error` results that mean "try again" surface
+/// as `io::ErrorKind::WouldBlock` rather than as an opaque failure. The
+/// wrapped `SSL` (and the `BIO` bridging it to `S`) are freed in `Drop`,
+/// replacing the fifteen manual `SSL_free`/`SSL_CTX_free` call sites the
+/// raw-FFI example needed on its error paths.
+pub struct SslStream<S> {
+    ssl: *mut SSL,
+    // Owned by `ssl` once `SSL_set_bio` runs (see `new`), so `SSL_free`
+    // frees the `BIO` itself; we only need the pointer to poll for stashed
+    // I/O errors and to flush, and to free the Rust-side `State<S>` that
+    // `BIO_free` doesn't know about (see bio.rs).
+    bio: *mut BIO,
+    _marker: PhantomData<S>,
+}
+
+impl<S: Read + Write> SslStream<S> {
+    /// Takes ownership of an `SSL` and a `Bio<S>`, calling `SSL_set_bio` to
+    /// hand the `BIO` to OpenSSL. Used internally by the connector; most
+    /// callers should go through `SslConnector::connect` instead.
+    pub(crate) unsafe fn new(ssl: *mut SSL, bio: Bio<S>) -> SslStream<S> {
+        let bio = bio.into_raw();
+        SSL_set_bio(ssl, bio, bio);
+        SslStream {
+            ssl,
+            bio,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The raw `SSL*`, for the connector's handshake loop which needs to
+    /// call `SSL_connect`/`SSL_get_error` directly before the stream is
+    /// handed back to the caller.
+    pub(crate) fn ssl_ptr(&self) -> *mut SSL {
+        self.ssl
+    }
+
+    fn map_io_error(&mut self, ret: i32) -> io::Error {
+        let code = unsafe { SSL_get_error(self.ssl, ret) };
+        if let Some(err) = unsafe { Bio::<S>::take_error(self.bio) } {
+            return err;
+        }
+        match code as u32 {
+            SSL_ERROR_WANT_READ | SSL_ERROR_WANT_WRITE => {
+                io::Error::new(io::ErrorKind::WouldBlock, "SSL operation would block")
+            }
+            SSL_ERROR_ZERO_RETURN => {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "SSL connection closed")
+            }
+            _ => io::Error::new(io::ErrorKind::Other, crate::error::ErrorStack::get()),
+        }
+    }
+}
+
+impl<S: Read + Write> Read for SslStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let ret = unsafe { SSL_read(self.ssl, buf.as_mut_ptr() as *mut _, buf.len() as i32) };
+            if ret > 0 {
+                return Ok(ret as usize);
+            }
+            let code = unsafe { SSL_get_error(self.ssl, ret) };
+            if code as u32 == SSL_ERROR_ZERO_RETURN {
+                return Ok(0);
+            }
+            return Err(self.map_io_error(ret));
+        }
+    }
+}
+
+impl<S: Read + Write> Write for SslStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let ret = unsafe { SSL_write(self.ssl, buf.as_ptr() as *const _, buf.len() as i32) };
+        if ret > 0 {
+            return Ok(ret as usize);
+        }
+        Err(self.map_io_error(ret))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        const BIO_CTRL_FLUSH: i32 = 11;
+        unsafe {
+            BIO_ctrl(self.bio, BIO_CTRL_FLUSH, 0, std::ptr::null_mut());
+        }
+        if let Some(err) = unsafe { Bio::<S>::take_error(self.bio) } {
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+impl<S> Drop for SslStream<S> {
+    fn drop(&mut self) {
+        unsafe {
+            // `BIO_free` (called transitively by `SSL_free` below, which
+            // owns the BIO chain since `SSL_set_bio`) doesn't know about the
+            // Rust-side `State<S>` stashed via `BIO_set_data`, so free that
+            // first while the BIO is still valid.
+            Bio::<S>::free_state(self.bio);
+            SSL_free(self.ssl);
+        }
+    }
+}
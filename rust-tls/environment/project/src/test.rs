@@ -0,0 +1,148 @@
+//! An embedded TLS test-server harness.
+//!
+//! Exercises `SslConnector`/`SslStream` against a real handshake without
+//! reaching the live internet (the raw-FFI example this crate replaces
+//! depended on reaching `www.rust-lang.org`). The root CA and the leaf
+//! certs it issued are baked into the test binary via `include_bytes!`, so
+//! runs are deterministic and offline. Gated behind the `test-support`
+//! feature since it pulls in a background-thread server that production
+//! builds have no use for.
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::bindings::*;
+
+const ROOT_CA_PEM: &[u8] = include_bytes!("test/certs/root-ca.pem");
+const LEAF_CERT_PEM: &[u8] = include_bytes!("test/certs/leaf-cert.pem");
+const LEAF_KEY_PEM: &[u8] = include_bytes!("test/certs/leaf-key.pem");
+const EXPIRED_CERT_PEM: &[u8] = include_bytes!("test/certs/expired-cert.pem");
+const EXPIRED_KEY_PEM: &[u8] = include_bytes!("test/certs/expired-key.pem");
+
+/// The root CA that signed both test leaf certs below. Trust only this (via
+/// `SslConnectorBuilder::add_trusted_cert_pem`) to exercise verification
+/// against `Server` deterministically, independent of the system trust
+/// store.
+pub fn root_ca_pem() -> &'static [u8] {
+    ROOT_CA_PEM
+}
+
+/// Which leaf certificate a `Server` presents during the handshake.
+pub enum Leaf {
+    /// Valid for `localhost`/`127.0.0.1`, signed by `root_ca_pem()`.
+    Valid,
+    /// Same subject and issuer, but its validity window is in the past —
+    /// for exercising the expired-cert rejection path.
+    Expired,
+}
+
+/// A TLS server bound on an ephemeral localhost port. Each accepted
+/// connection runs an `SSL_accept` handshake on a background thread; there's
+/// no application protocol past the handshake, since tests only need to
+/// observe whether a client considered the server (un)trustworthy.
+pub struct Server {
+    addr: SocketAddr,
+    ctx: *mut SSL_CTX,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Binds the server and starts accepting connections in the
+    /// background. Panics on setup failure — this is test-only code with no
+    /// caller to hand a `Result` to.
+    pub fn start(leaf: Leaf) -> Server {
+        let (cert_pem, key_pem) = match leaf {
+            Leaf::Valid => (LEAF_CERT_PEM, LEAF_KEY_PEM),
+            Leaf::Expired => (EXPIRED_CERT_PEM, EXPIRED_KEY_PEM),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind test server");
+        let addr = listener.local_addr().expect("failed to read bound addr");
+
+        let ctx = unsafe { new_server_ctx(cert_pem, key_pem) };
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_in_thread = shutdown.clone();
+        // Raw pointers aren't `Send`; pass the address into the thread.
+        let ctx_addr = ctx as usize;
+
+        let handle = thread::spawn(move || {
+            let ctx = ctx_addr as *mut SSL_CTX;
+            for stream in listener.incoming() {
+                if shutdown_in_thread.load(Ordering::Acquire) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    accept(ctx, stream);
+                }
+            }
+        });
+
+        Server {
+            addr,
+            ctx,
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        // The accept loop is blocked in `listener.incoming()`; a dummy
+        // connection wakes it up so it can observe the shutdown flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        // Only safe to free once the accept thread (the only other user of
+        // `ctx`) has exited, hence the join above.
+        unsafe { SSL_CTX_free(self.ctx) };
+    }
+}
+
+unsafe fn new_server_ctx(cert_pem: &[u8], key_pem: &[u8]) -> *mut SSL_CTX {
+    let ctx = SSL_CTX_new(TLS_server_method());
+    assert!(!ctx.is_null(), "SSL_CTX_new returned null");
+
+    let cert_bio = BIO_new_mem_buf(cert_pem.as_ptr() as *const _, cert_pem.len() as i32);
+    let cert = PEM_read_bio_X509(cert_bio, std::ptr::null_mut(), None, std::ptr::null_mut());
+    assert!(!cert.is_null(), "failed to parse embedded test leaf cert");
+    assert_eq!(SSL_CTX_use_certificate(ctx, cert), 1);
+    X509_free(cert);
+    BIO_free(cert_bio);
+
+    let key_bio = BIO_new_mem_buf(key_pem.as_ptr() as *const _, key_pem.len() as i32);
+    let key = PEM_read_bio_PrivateKey(key_bio, std::ptr::null_mut(), None, std::ptr::null_mut());
+    assert!(!key.is_null(), "failed to parse embedded test leaf key");
+    assert_eq!(SSL_CTX_use_PrivateKey(ctx, key), 1);
+    EVP_PKEY_free(key);
+    BIO_free(key_bio);
+
+    ctx
+}
+
+fn accept(ctx: *mut SSL_CTX, stream: TcpStream) {
+    let fd = stream.as_raw_fd();
+    unsafe {
+        let ssl = SSL_new(ctx);
+        let bio = BIO_new_socket(fd, 0);
+        SSL_set_bio(ssl, bio, bio);
+        SSL_set_accept_state(ssl);
+        // The handshake's outcome is observed by the client under test, not
+        // here; a failed `SSL_accept` (e.g. the client rejected the leaf
+        // during verification and bailed) is expected for the rejection
+        // scenarios and isn't an error from the server's point of view.
+        SSL_accept(ssl);
+        SSL_free(ssl);
+    }
+    drop(stream);
+}
@@ -0,0 +1,62 @@
+//! Integration tests for `SslConnector` against the embedded test-server
+//! harness in `rust_tls::test`. These are the tests `Server` was built for:
+//! a successful handshake, a wrong-hostname rejection, and an expired-cert
+//! rejection, all against certs baked into the test binary rather than the
+//! live internet. Requires the `test-support` feature.
+
+use std::net::TcpStream;
+
+use rust_tls::test::{root_ca_pem, Leaf, Server};
+use rust_tls::{HandshakeError, SslConnectorBuilder};
+
+#[test]
+fn handshake_succeeds_for_trusted_cert_and_matching_hostname() {
+    let server = Server::start(Leaf::Valid);
+
+    let connector = SslConnectorBuilder::new()
+        .expect("failed to create connector builder")
+        .add_trusted_cert_pem(root_ca_pem())
+        .expect("failed to trust test root CA")
+        .build();
+
+    let stream = TcpStream::connect(server.addr()).expect("failed to connect to test server");
+    connector
+        .connect("localhost", stream)
+        .expect("handshake against a trusted, matching-hostname leaf should succeed");
+}
+
+#[test]
+fn handshake_rejects_wrong_hostname() {
+    let server = Server::start(Leaf::Valid);
+
+    let connector = SslConnectorBuilder::new()
+        .expect("failed to create connector builder")
+        .add_trusted_cert_pem(root_ca_pem())
+        .expect("failed to trust test root CA")
+        .build();
+
+    let stream = TcpStream::connect(server.addr()).expect("failed to connect to test server");
+    match connector.connect("totally-wrong-host.example", stream) {
+        Err(HandshakeError::Failure(_)) => {}
+        Err(other) => panic!("expected Failure, got {:?}", other),
+        Ok(_) => panic!("handshake succeeded despite a hostname mismatch"),
+    }
+}
+
+#[test]
+fn handshake_rejects_expired_cert() {
+    let server = Server::start(Leaf::Expired);
+
+    let connector = SslConnectorBuilder::new()
+        .expect("failed to create connector builder")
+        .add_trusted_cert_pem(root_ca_pem())
+        .expect("failed to trust test root CA")
+        .build();
+
+    let stream = TcpStream::connect(server.addr()).expect("failed to connect to test server");
+    match connector.connect("localhost", stream) {
+        Err(HandshakeError::Failure(_)) => {}
+        Err(other) => panic!("expected Failure, got {:?}", other),
+        Ok(_) => panic!("handshake succeeded despite an expired leaf cert"),
+    }
+}